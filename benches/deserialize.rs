@@ -0,0 +1,27 @@
+//! Cost of deserializing `GfyItem`, the bulk of every feed response.
+//!
+//!     cargo bench --bench deserialize
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use gfycat::GfyItem;
+
+const SAMPLE: &str = include_str!("../test_data/accomplishedfondkingsnake.json");
+
+fn gfy_item(c: &mut Criterion) {
+    let mut group = c.benchmark_group("gfy_item");
+    let sample: serde_json::Value = serde_json::from_str(SAMPLE).unwrap();
+    let item = serde_json::to_string(&sample["gfyItem"]).unwrap();
+    let feed = format!("[{}]", vec![item.as_str(); 100].join(","));
+
+    group.bench_function("single", |b| {
+        b.iter(|| serde_json::from_str::<GfyItem>(black_box(&item)).unwrap())
+    });
+    group.bench_function("array_of_100", |b| {
+        b.iter(|| serde_json::from_str::<Vec<GfyItem>>(black_box(&feed)).unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, gfy_item);
+criterion_main!(benches);
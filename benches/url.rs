@@ -0,0 +1,37 @@
+//! Cost of building an endpoint url per request.
+//!
+//! `Api` used to format the base and path into a `String` that reqwest then
+//! parsed; it now keeps the base parsed and only resolves the path against it.
+//!
+//!     cargo bench --bench url
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use reqwest::Url;
+
+const ENDPOINT: &str = "https://api.gfycat.com/v1/";
+
+fn endpoint_url(c: &mut Criterion) {
+    let mut group = c.benchmark_group("endpoint_url");
+    let path = "gfycats/accomplishedfondkingsnake/related";
+
+    group.bench_function("format_and_parse", |b| {
+        b.iter(|| {
+            let endpoint = format!(
+                "{}/{}",
+                black_box(ENDPOINT).trim_end_matches('/'),
+                black_box(path)
+            );
+            Url::parse(&endpoint).unwrap()
+        })
+    });
+
+    let base = Url::parse(ENDPOINT).unwrap();
+    group.bench_function("join_parsed_base", |b| {
+        b.iter(|| black_box(&base).join(black_box(path)).unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, endpoint_url);
+criterion_main!(benches);
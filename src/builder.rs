@@ -0,0 +1,748 @@
+use crate::{error, metrics::Metrics, transport::Transport, Api, LoadCredentials, TokenResponse};
+use std::sync::Arc;
+use std::time;
+
+/// `User-Agent` sent when none is configured
+const DEFAULT_USER_AGENT: &str = concat!("gfycat-rs/", env!("CARGO_PKG_VERSION"));
+
+/// Builder for an `Api` handler when the defaults of `Api::new` are not enough
+///
+/// For batch workloads issuing many concurrent requests, a
+/// `pool_max_idle_per_host` around the number of requests in flight (16-32 is
+/// typical) and a `tcp_keepalive` of about a minute keep connections warm
+/// between bursts instead of re-handshaking TLS.
+#[derive(Debug, Default)]
+pub struct ApiBuilder {
+    scope: Option<String>,
+    client: Option<reqwest::Client>,
+    base: Option<reqwest::Url>,
+    transport: Option<Arc<dyn Transport>>,
+    metrics: Option<Arc<dyn Metrics>>,
+    etag_cache: bool,
+    rate_limit: Option<(f64, u32)>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<time::Duration>,
+    tcp_keepalive: Option<time::Duration>,
+    gzip: Option<bool>,
+    brotli: Option<bool>,
+    raw_downloads: bool,
+    max_redirects: Option<usize>,
+    user_agent: Option<String>,
+    #[cfg(any(feature = "native-tls", feature = "rustls"))]
+    root_certificates: Vec<reqwest::Certificate>,
+    timeout: Option<time::Duration>,
+    max_retries: u32,
+    concurrency: Option<usize>,
+    max_body_size: Option<u64>,
+    refresh_leeway: Option<time::Duration>,
+}
+
+impl ApiBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// OAuth2 scope sent along with the token request. No scope is sent by default.
+    ///
+    /// gfycat does not publish a per-endpoint scope table. A `client_credentials`
+    /// token covers the public read endpoints (`info`, `trending_categories`,
+    /// `user_details`, `user_exists`), while the `me/...` endpoints
+    /// (`self_details`, `email_verified`, `send_email_verification`, following
+    /// and folders) act on behalf of a user. If those return
+    /// `ApiError::Unauthorized`, request the scope your application was registered with.
+    pub fn scope(mut self, scope: &str) -> Self {
+        self.scope = Some(scope.to_owned());
+        self
+    }
+
+    /// Use an already configured `reqwest::Client` for every request. The
+    /// transport options of this builder are ignored when a client is supplied.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Send every request, the token request included, to `base` instead of
+    /// `https://api.gfycat.com/v1/`, e.g. a proxy or a mock server in tests.
+    /// A missing trailing `/` is added.
+    ///
+    /// Panics if `base` cannot have a path, like `mailto:` urls.
+    pub fn base_url(mut self, mut base: reqwest::Url) -> Self {
+        assert!(
+            !base.cannot_be_a_base(),
+            "api base must be a hierarchical url"
+        );
+        if !base.path().ends_with('/') {
+            let path = format!("{}/", base.path());
+            base.set_path(&path);
+        }
+        self.base = Some(base);
+        self
+    }
+
+    /// Send requests through `transport` instead of the `reqwest::Client`.
+    /// Requests are still built with the client, so its default headers apply.
+    pub fn transport<T: Transport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Report every request (endpoint, status, error kind and latency) to
+    /// `metrics`, e.g. to feed prometheus counters. Off by default.
+    pub fn metrics<M: Metrics + 'static>(mut self, metrics: M) -> Self {
+        self.metrics = Some(Arc::new(metrics));
+        self
+    }
+
+    /// Revalidate repeated GET requests with `If-None-Match` and answer a
+    /// `304` from memory, see `cache::EtagCache`. Off by default; worth enabling
+    /// when polling `info` or feeds. The cache keeps the 256 most recent
+    /// responses and skips bodies over `max_body_size`; it cannot be cleared,
+    /// pass your own `EtagCache` to `transport` for that.
+    pub fn etag_cache(mut self, enable: bool) -> Self {
+        self.etag_cache = enable;
+        self
+    }
+
+    /// Send at most `per_second` requests per second, allowing bursts of up to
+    /// `burst` requests, see `ratelimit::RateLimit`. Requests over the limit
+    /// wait instead of being rejected by gfycat. Off by default.
+    ///
+    /// Panics unless `per_second` is positive and `burst` at least 1.
+    pub fn rate_limit(mut self, per_second: f64, burst: u32) -> Self {
+        assert!(per_second > 0.0, "rate limit must be positive");
+        assert!(burst >= 1, "burst must allow at least one request");
+        self.rate_limit = Some((per_second, burst));
+        self
+    }
+
+    /// Maximum number of idle connections kept open to gfycat
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// How long an idle pooled connection is kept before being closed
+    pub fn pool_idle_timeout(mut self, timeout: time::Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Interval of TCP keep-alive probes on open connections
+    pub fn tcp_keepalive(mut self, interval: time::Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Advertise gzip support and transparently decompress gzip responses.
+    /// Enabled by default; worth keeping on when pulling large feeds over slow links.
+    pub fn gzip(mut self, enable: bool) -> Self {
+        self.gzip = Some(enable);
+        self
+    }
+
+    /// Advertise brotli support and transparently decompress brotli responses.
+    /// Enabled by default.
+    pub fn brotli(mut self, enable: bool) -> Self {
+        self.brotli = Some(enable);
+        self
+    }
+
+    /// Decompress gzip and brotli encoded media in `Api::download`. Enabled by
+    /// default.
+    ///
+    /// When disabled, downloads go through a second client that neither
+    /// advertises nor decodes compression, so the bytes are exactly those the
+    /// CDN served and their length matches the `Content-Length` that
+    /// `verify_renditions` reads. Api calls keep the `gzip`/`brotli` settings.
+    /// Has no effect when a client is supplied through `client`.
+    pub fn decompress_downloads(mut self, enable: bool) -> Self {
+        self.raw_downloads = !enable;
+        self
+    }
+
+    /// Follow at most `max` redirects, or none when `max` is 0, in which case
+    /// the `3xx` response is returned so its `Location` can be inspected (see
+    /// `Api::request`). Up to 10 redirects are followed by default.
+    pub fn max_redirects(mut self, max: usize) -> Self {
+        self.max_redirects = Some(max);
+        self
+    }
+
+    /// `User-Agent` sent with every request, `gfycat-rs/<version>` by default.
+    /// A descriptive agent lets gfycat identify your traffic instead of
+    /// throttling it as a generic client.
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_owned());
+        self
+    }
+
+    /// Trust `certificate` as an additional root, e.g. the certificate of a
+    /// corporate proxy that intercepts TLS. Load it with
+    /// `reqwest::Certificate::from_pem` or `from_der`. May be called repeatedly.
+    ///
+    /// Requires the `native-tls` (default) or `rustls` feature.
+    #[cfg(any(feature = "native-tls", feature = "rustls"))]
+    pub fn add_root_certificate(mut self, certificate: reqwest::Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Default time limit for a whole request, from connecting until the body
+    /// has been read. None by default. Long running calls such as
+    /// `upload_file` can override it per call.
+    pub fn timeout(mut self, timeout: time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Retry idempotent requests (GET, HEAD, PUT, DELETE) that fail with a 5xx
+    /// up to `retries` times, with exponential backoff. No retries by default.
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Requests batch methods (`info_many`, `user_details_many`, `follow_many`,
+    /// ...) keep in flight when called with `None` as their limit, and the
+    /// limit of those without one (`merged_feed`, `FeedPage::enrich`,
+    /// `GfyItem::verify_renditions`, `processing_gfycats`). 8 by default.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = Some(concurrency);
+        self
+    }
+
+    /// Fail api calls with `ApiError::BodyTooLarge` instead of reading a
+    /// response body over `bytes`, guarding against a misbehaving server or
+    /// proxy. Unlimited by default. Applies to the json answers the methods
+    /// parse, not to `download` or the raw `Api::request`.
+    pub fn max_body_size(mut self, bytes: u64) -> Self {
+        self.max_body_size = Some(bytes);
+        self
+    }
+
+    /// Refresh the token in `Api::reauthorize_if_needed` once it expires within
+    /// `leeway`, so requests sent right after are not made with a token that
+    /// runs out in flight. 60 seconds by default.
+    ///
+    /// Refreshing is manual: requests do not check the leeway, so call
+    /// `reauthorize_if_needed` (or check `needs_reauthorize`) before sending.
+    pub fn refresh_leeway(mut self, leeway: time::Duration) -> Self {
+        self.refresh_leeway = Some(leeway);
+        self
+    }
+
+    /// Authenticate with gfycat and create the api handler
+    pub async fn build(
+        self,
+        client_id: &str,
+        client_secret: &str,
+    ) -> Result<Api, error::AuthError> {
+        let credentials = LoadCredentials {
+            client_id: client_id.to_owned(),
+            client_secret: client_secret.to_owned(),
+        };
+        self.build_from_owned_credentials(credentials).await
+    }
+
+    pub async fn build_from_credentials(
+        self,
+        credentials: &LoadCredentials,
+    ) -> Result<Api, error::AuthError> {
+        self.build_from_owned_credentials(credentials.clone()).await
+    }
+
+    /// Authenticate and keep `credentials` on the handler so `Api::reauthorize`
+    /// works without the caller holding on to them
+    pub async fn build_from_owned_credentials(
+        self,
+        credentials: LoadCredentials,
+    ) -> Result<Api, error::AuthError> {
+        // reqwest panics deep inside hyper without a reactor, fail clearly instead
+        if tokio::runtime::Handle::try_current().is_err() {
+            return Err(error::AuthError::NoRuntime);
+        }
+
+        let client = self.http_client()?;
+        let transport = self.transport_for(&client);
+
+        let token = crate::request_token(
+            &client,
+            &*transport,
+            &self.base(),
+            &credentials,
+            self.scope.as_deref(),
+        )
+        .await?;
+
+        let mut api = self.finish(client, token)?;
+        api.credentials = Some(credentials);
+        Ok(api)
+    }
+
+    /// Request a token for `credentials` with this builder's settings and
+    /// discard it, see `LoadCredentials::validate`
+    pub async fn validate(self, credentials: &LoadCredentials) -> Result<(), error::AuthError> {
+        if tokio::runtime::Handle::try_current().is_err() {
+            return Err(error::AuthError::NoRuntime);
+        }
+
+        let client = self.http_client()?;
+        let transport = self.transport_for(&client);
+
+        crate::request_token(
+            &client,
+            &*transport,
+            &self.base(),
+            credentials,
+            self.scope.as_deref(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Blocking version of `build` for callers without an async runtime. A
+    /// single threaded tokio runtime is started just for the token request.
+    ///
+    /// Panics if called from inside a tokio runtime; use `build` there. Making
+    /// calls with the returned `Api` still requires a tokio runtime.
+    pub fn build_blocking(
+        self,
+        client_id: &str,
+        client_secret: &str,
+    ) -> Result<Api, error::AuthError> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+
+        runtime.block_on(self.build(client_id, client_secret))
+    }
+
+    /// The configured api base, `ENDPOINT` by default
+    fn base(&self) -> reqwest::Url {
+        self.base.clone().unwrap_or_else(crate::base_url)
+    }
+
+    /// The supplied client, or a new one with the configured transport options
+    fn http_client(&self) -> Result<reqwest::Client, reqwest::Error> {
+        match &self.client {
+            Some(client) => Ok(client.clone()),
+            None => self.client_builder().build(),
+        }
+    }
+
+    /// A separate client for downloads when they must not be decompressed
+    fn download_client(&self) -> Result<Option<reqwest::Client>, reqwest::Error> {
+        if !self.raw_downloads || self.client.is_some() {
+            return Ok(None);
+        }
+
+        let client = self.client_builder().gzip(false).brotli(false).build()?;
+        Ok(Some(client))
+    }
+
+    /// A client builder with the configured transport options
+    fn client_builder(&self) -> reqwest::ClientBuilder {
+        let user_agent = self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+        let mut builder = reqwest::Client::builder().user_agent(user_agent);
+
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(interval) = self.tcp_keepalive {
+            builder = builder.tcp_keepalive(interval);
+        }
+        if let Some(enable) = self.gzip {
+            builder = builder.gzip(enable);
+        }
+        if let Some(enable) = self.brotli {
+            builder = builder.brotli(enable);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        match self.max_redirects {
+            Some(0) => builder = builder.redirect(reqwest::redirect::Policy::none()),
+            Some(max) => builder = builder.redirect(reqwest::redirect::Policy::limited(max)),
+            None => {}
+        }
+        #[cfg(any(feature = "native-tls", feature = "rustls"))]
+        for certificate in &self.root_certificates {
+            builder = builder.add_root_certificate(certificate.clone());
+        }
+
+        builder
+    }
+
+    /// The supplied transport, or `client` itself, behind the rate limit and
+    /// etag cache if enabled
+    fn transport_for(&self, client: &reqwest::Client) -> Arc<dyn Transport> {
+        let mut transport: Arc<dyn Transport> = match &self.transport {
+            Some(transport) => transport.clone(),
+            None => Arc::new(client.clone()),
+        };
+
+        if let Some((per_second, burst)) = self.rate_limit {
+            transport = Arc::new(crate::ratelimit::RateLimit::new(
+                transport, per_second, burst,
+            ));
+        }
+
+        if self.etag_cache {
+            let cache = crate::cache::EtagCache::new(transport);
+            match self.max_body_size {
+                Some(limit) => Arc::new(cache.max_body_size(limit)),
+                None => Arc::new(cache),
+            }
+        } else {
+            transport
+        }
+    }
+
+    /// Create the api handler once a token has been granted
+    fn finish(
+        self,
+        client: reqwest::Client,
+        token: TokenResponse,
+    ) -> Result<Api, error::AuthError> {
+        let transport = self.transport_for(&client);
+        let downloads = self.download_client()?;
+        let mut api = token.into_api(client, transport)?;
+        api.base = self.base();
+        if let Some(client) = downloads {
+            api.downloads = self.transport_for(&client);
+        }
+        api.scope = self.scope;
+        api.max_retries = self.max_retries;
+        if let Some(concurrency) = self.concurrency {
+            api.concurrency = concurrency;
+        }
+        api.max_body_size = self.max_body_size;
+        if let Some(leeway) = self.refresh_leeway {
+            api.refresh_leeway = leeway;
+        }
+        api.metrics = self.metrics;
+        Ok(api)
+    }
+}
+
+#[cfg(test)]
+fn credentials() -> LoadCredentials {
+    LoadCredentials {
+        client_id: "id".into(),
+        client_secret: "secret".into(),
+    }
+}
+
+#[test]
+fn token_form_scope() {
+    let form = credentials().token_form(Some("upload"));
+    assert_eq! {form["scope"], "upload"};
+    assert_eq! {form["grant_type"], "client_credentials"};
+}
+
+#[test]
+fn token_form_no_scope() {
+    let form = credentials().token_form(None);
+    assert! {form.get("scope").is_none()};
+}
+
+#[cfg(test)]
+fn mock_token() -> TokenResponse {
+    TokenResponse {
+        token_type: crate::TokenType::Bearer,
+        expires_in: 3600,
+        access_token: "mock".into(),
+        ..TokenResponse::default()
+    }
+}
+
+#[test]
+fn supplied_client_is_used() {
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, _) = crate::init_mock();
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("x-client", "custom".parse().unwrap());
+    let custom = reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .unwrap();
+
+    let builder = ApiBuilder::new().client(custom).pool_max_idle_per_host(4);
+    let client = builder.http_client().unwrap();
+    let mut api = builder.finish(client, mock_token()).unwrap();
+    api.base = reqwest::Url::parse(&(server.uri() + "/")).unwrap();
+
+    let body = serde_json::json! {{"gfyItem": crate::sample_gfy_item()}};
+    tk.block_on(
+        Mock::given(method("GET"))
+            .and(path("/gfycats/accomplishedfondkingsnake"))
+            .and(header("x-client", "custom"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .expect(1)
+            .mount(&server),
+    );
+
+    let left = tk.block_on(api.info("accomplishedfondkingsnake"));
+    assert! {left.is_ok()};
+}
+
+#[test]
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+fn root_certificate() {
+    let pem = include_bytes!("../test_data/corporate_root.pem");
+    let certificate = reqwest::Certificate::from_pem(pem).unwrap();
+
+    let builder = ApiBuilder::new().add_root_certificate(certificate.clone());
+    assert! {builder.http_client().is_ok()};
+
+    // a supplied client carries its own roots
+    use wiremock::matchers::{header, method};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, _) = crate::init_mock();
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("x-client", "corporate".parse().unwrap());
+    let custom = reqwest::Client::builder()
+        .add_root_certificate(certificate)
+        .default_headers(headers)
+        .build()
+        .unwrap();
+
+    let builder = ApiBuilder::new().client(custom);
+    let client = builder.http_client().unwrap();
+    let mut api = builder.finish(client, mock_token()).unwrap();
+    api.base = reqwest::Url::parse(&(server.uri() + "/")).unwrap();
+
+    tk.block_on(
+        Mock::given(method("GET"))
+            .and(header("x-client", "corporate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(crate::sample_user()))
+            .expect(1)
+            .mount(&server),
+    );
+    assert! {tk.block_on(api.user_details(42)).is_ok()};
+}
+
+// run under each backend: `cargo test` and
+// `cargo test --no-default-features --features rustls`
+#[test]
+fn tls_backend_builds() {
+    assert! {ApiBuilder::new().http_client().is_ok()};
+}
+
+#[test]
+fn pool_settings_build() {
+    let builder = ApiBuilder::new()
+        .pool_max_idle_per_host(32)
+        .pool_idle_timeout(time::Duration::from_secs(90))
+        .tcp_keepalive(time::Duration::from_secs(60));
+    assert! {builder.http_client().is_ok()};
+}
+
+#[test]
+fn gzip_feed() {
+    use std::io::Write;
+    use wiremock::matchers::{header_regex, method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, _) = crate::init_mock();
+    let body = serde_json::json! {{"gfycats": [crate::sample_gfy_item()], "cursor": ""}};
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(body.to_string().as_bytes()).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let builder = ApiBuilder::new().gzip(true);
+    let client = builder.http_client().unwrap();
+    let mut api = builder.finish(client, mock_token()).unwrap();
+    api.base = reqwest::Url::parse(&(server.uri() + "/")).unwrap();
+
+    tk.block_on(
+        Mock::given(method("GET"))
+            .and(path("/gfycats/search"))
+            .and(header_regex("accept-encoding", "gzip"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-encoding", "gzip")
+                    .insert_header("content-type", "application/json")
+                    .set_body_bytes(compressed),
+            )
+            .mount(&server),
+    );
+
+    let left = tk.block_on(api.search("nyc", 1, None)).unwrap();
+    assert_eq! {left.gfycats[0].gfy_id, "accomplishedfondkingsnake"};
+}
+
+#[test]
+fn authenticate_against_base_url() {
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, _) = crate::init_mock();
+    let token =
+        serde_json::json! {{"token_type": "bearer", "expires_in": 3600, "access_token": "mocked"}};
+    let body = serde_json::json! {{"gfyItem": crate::sample_gfy_item()}};
+    tk.block_on(async {
+        Mock::given(method("POST"))
+            .and(path("/v1/oauth/token"))
+            .and(body_partial_json(serde_json::json! {{"client_id": "id"}}))
+            .respond_with(ResponseTemplate::new(200).set_body_json(token))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/gfycats/accomplishedfondkingsnake"))
+            .and(header("authorization", "Bearer mocked"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .expect(1)
+            .mount(&server)
+            .await;
+    });
+
+    let base = reqwest::Url::parse(&(server.uri() + "/v1")).unwrap();
+    let api = tk
+        .block_on(ApiBuilder::new().base_url(base).build("id", "secret"))
+        .unwrap();
+    let left = tk.block_on(api.info("accomplishedfondkingsnake"));
+    assert! {left.is_ok()};
+}
+
+#[test]
+fn redirect_policy() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, _) = crate::init_mock();
+    tk.block_on(async {
+        Mock::given(method("GET"))
+            .and(path("/moved"))
+            .respond_with(ResponseTemplate::new(302).insert_header("location", "/target"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/target"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+    });
+
+    let request = |builder: ApiBuilder| {
+        let client = builder.http_client().unwrap();
+        let mut api = builder.finish(client, mock_token()).unwrap();
+        api.base = reqwest::Url::parse(&(server.uri() + "/")).unwrap();
+        tk.block_on(api.request(reqwest::Method::GET, "moved", None))
+            .unwrap()
+    };
+
+    let followed = request(ApiBuilder::new());
+    assert_eq! {followed.status(), 200};
+    assert_eq! {followed.url().path(), "/target"};
+
+    let stopped = request(ApiBuilder::new().max_redirects(0));
+    assert_eq! {stopped.status(), 302};
+    assert_eq! {stopped.headers()["location"], "/target"};
+}
+
+#[test]
+fn raw_downloads() {
+    use std::io::Write;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, _) = crate::init_mock();
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(b"GIF89a frames").unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    tk.block_on(
+        Mock::given(method("GET"))
+            .and(path("/AccomplishedFondKingsnake.gif"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-encoding", "gzip")
+                    .set_body_bytes(compressed.clone()),
+            )
+            .mount(&server),
+    );
+    let url = server.uri() + "/AccomplishedFondKingsnake.gif";
+
+    let download = |builder: ApiBuilder| {
+        let client = builder.http_client().unwrap();
+        let api = builder.finish(client, mock_token()).unwrap();
+        tk.block_on(api.download(&url, None, None)).unwrap()
+    };
+
+    assert_eq! {download(ApiBuilder::new()), b"GIF89a frames"};
+    assert_eq! {download(ApiBuilder::new().decompress_downloads(false)), compressed};
+}
+
+#[cfg(test)]
+fn user_agent_sent(builder: ApiBuilder, expected: &str) {
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, _) = crate::init_mock();
+    let client = builder.http_client().unwrap();
+    let mut api = builder.finish(client, mock_token()).unwrap();
+    api.base = reqwest::Url::parse(&(server.uri() + "/")).unwrap();
+
+    tk.block_on(
+        Mock::given(method("GET"))
+            .and(path("/users/egster"))
+            .and(header("user-agent", expected))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server),
+    );
+
+    let left = tk.block_on(api.user_exists("@egster"));
+    assert! {left.unwrap()};
+}
+
+#[test]
+fn user_agent() {
+    user_agent_sent(ApiBuilder::new().user_agent("my-app/1.0"), "my-app/1.0");
+}
+
+#[test]
+fn default_user_agent() {
+    user_agent_sent(ApiBuilder::new(), DEFAULT_USER_AGENT);
+}
+
+#[test]
+fn build_blocking_outside_runtime() {
+    let token = r#"{"token_type": "bearer", "expires_in": 3600, "access_token": "blocking"}"#;
+    let transport = crate::transport::CannedTransport::new(200, token);
+
+    let api = ApiBuilder::new()
+        .transport(transport)
+        .build_blocking("id", "secret")
+        .unwrap();
+    assert_eq! {api.token, "Bearer blocking"};
+}
+
+#[test]
+fn build_without_runtime() {
+    let left = futures::executor::block_on(ApiBuilder::new().build("id", "secret"));
+    assert! {matches!(left, Err(error::AuthError::NoRuntime))};
+}
+
+#[test]
+fn validate_credentials() {
+    let tk = tokio::runtime::Runtime::new().unwrap();
+    let token = r#"{"token_type": "bearer", "expires_in": 3600, "access_token": "valid"}"#;
+
+    let valid = ApiBuilder::new().transport(crate::transport::CannedTransport::new(200, token));
+    assert! {tk.block_on(valid.validate(&credentials())).is_ok()};
+
+    let body = r#"{"errorMessage": {"code": "InvalidClient"}}"#;
+    let invalid = ApiBuilder::new().transport(crate::transport::CannedTransport::new(401, body));
+    let left = tk.block_on(invalid.validate(&credentials()));
+    assert! {matches!(left, Err(error::AuthError::InvalidCredentials))};
+}
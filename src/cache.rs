@@ -0,0 +1,325 @@
+use crate::transport::{ResponseFuture, Transport};
+use futures::stream::{self, StreamExt};
+use reqwest::header::{HeaderMap, ETAG, IF_NONE_MATCH};
+use reqwest::ResponseBuilderExt;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Responses an `EtagCache` keeps unless `capacity` says otherwise
+const DEFAULT_CAPACITY: usize = 256;
+
+/// A cached response body and the headers it came with
+#[derive(Debug, Clone)]
+struct Entry {
+    etag: reqwest::header::HeaderValue,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+/// Transport that revalidates GET requests with `If-None-Match`.
+///
+/// Every GET response carrying an `ETag` is kept in memory by url. Repeating
+/// the request sends the stored tag, and a `304 Not Modified` answer is
+/// replaced with the stored `200` response, so callers polling `info` or a
+/// feed see the same result without the body being transferred again.
+///
+/// At most `capacity` responses are kept, the oldest being evicted first.
+/// Bodies larger than `max_body_size` are passed through without being cached
+/// or fully buffered.
+///
+/// `ApiBuilder::etag_cache` sets one up that the handler owns, so it cannot
+/// be cleared. To call `clear`, build the cache yourself, keep a clone of its
+/// `Arc` and hand the other to `ApiBuilder::transport`.
+#[derive(Debug)]
+pub struct EtagCache<T> {
+    inner: T,
+    capacity: usize,
+    max_body_size: Option<u64>,
+    entries: Mutex<Entries>,
+}
+
+/// Cached responses by url, and the urls in the order they were cached
+#[derive(Debug, Default)]
+struct Entries {
+    by_url: HashMap<String, Entry>,
+    order: VecDeque<String>,
+}
+
+impl Entries {
+    fn insert(&mut self, url: String, entry: Entry, capacity: usize) {
+        if self.by_url.insert(url.clone(), entry).is_some() {
+            self.order.retain(|cached| *cached != url);
+        }
+        self.order.push_back(url);
+
+        while self.order.len() > capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.by_url.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl<T: Transport> EtagCache<T> {
+    pub fn new(inner: T) -> Self {
+        EtagCache {
+            inner,
+            capacity: DEFAULT_CAPACITY,
+            max_body_size: None,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// Keep at most `capacity` responses. 256 by default.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Do not cache bodies larger than `bytes`. Unlimited by default;
+    /// `ApiBuilder` passes its `max_body_size`.
+    pub fn max_body_size(mut self, bytes: u64) -> Self {
+        self.max_body_size = Some(bytes);
+        self
+    }
+
+    /// Forget every cached response
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.by_url.clear();
+        entries.order.clear();
+    }
+
+    /// Number of cached responses
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().by_url.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    async fn execute_cached(
+        &self,
+        mut request: reqwest::Request,
+    ) -> reqwest::Result<reqwest::Response> {
+        if request.method() != reqwest::Method::GET {
+            return self.inner.execute(request).await;
+        }
+
+        let url = request.url().clone();
+        let cached = self
+            .entries
+            .lock()
+            .unwrap()
+            .by_url
+            .get(url.as_str())
+            .cloned();
+        if let Some(entry) = &cached {
+            request
+                .headers_mut()
+                .insert(IF_NONE_MATCH, entry.etag.clone());
+        }
+
+        let response = self.inner.execute(request).await?;
+
+        match (response.status(), cached) {
+            (reqwest::StatusCode::NOT_MODIFIED, Some(entry)) => {
+                Ok(rebuild(url, entry.headers, entry.body))
+            }
+            (reqwest::StatusCode::OK, _) if response.headers().contains_key(ETAG) => {
+                let headers = response.headers().clone();
+                let body = match self.read_limited(response).await? {
+                    Ok(body) => body,
+                    Err(too_large) => return Ok(too_large),
+                };
+
+                let entry = Entry {
+                    etag: headers[ETAG].clone(),
+                    headers: headers.clone(),
+                    body: body.clone(),
+                };
+                self.entries
+                    .lock()
+                    .unwrap()
+                    .insert(url.to_string(), entry, self.capacity);
+
+                Ok(rebuild(url, headers, body))
+            }
+            _ => Ok(response),
+        }
+    }
+}
+
+impl<T> EtagCache<T> {
+    /// Buffer the body of `response`, or once it passes `max_body_size` give
+    /// up and return an equivalent response that streams what was read
+    /// followed by the rest
+    async fn read_limited(
+        &self,
+        mut response: reqwest::Response,
+    ) -> reqwest::Result<Result<Vec<u8>, reqwest::Response>> {
+        let limit = match self.max_body_size {
+            Some(limit) => limit,
+            None => return Ok(Ok(response.bytes().await?.to_vec())),
+        };
+        if matches!(response.content_length(), Some(length) if length > limit) {
+            return Ok(Err(response));
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            body.extend_from_slice(&chunk);
+            if body.len() as u64 > limit {
+                return Ok(Err(resume(response, body)));
+            }
+        }
+
+        Ok(Ok(body))
+    }
+}
+
+impl<T: Transport> Transport for EtagCache<T> {
+    fn execute(&self, request: reqwest::Request) -> ResponseFuture<'_> {
+        Box::pin(self.execute_cached(request))
+    }
+}
+
+/// `response` with `read` put back in front of its remaining body
+fn resume(response: reqwest::Response, read: Vec<u8>) -> reqwest::Response {
+    let url = response.url().clone();
+    let headers = response.headers().clone();
+    let status = response.status();
+
+    let rest = response.bytes_stream();
+    let body = stream::once(async move { Ok(read.into()) }).chain(rest);
+
+    let mut resumed = http::Response::builder()
+        .status(status)
+        .url(url)
+        .body(reqwest::Body::wrap_stream(body))
+        .expect("response parts are valid");
+    *resumed.headers_mut() = headers;
+    reqwest::Response::from(resumed)
+}
+
+/// A `200` response from `url` with the given headers and a fully buffered body
+fn rebuild(url: reqwest::Url, headers: HeaderMap, body: Vec<u8>) -> reqwest::Response {
+    let mut response = http::Response::builder()
+        .url(url)
+        .body(body)
+        .expect("response parts are valid");
+    *response.headers_mut() = headers;
+    reqwest::Response::from(response)
+}
+
+#[test]
+fn not_modified_served_from_cache() {
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, mut api) = crate::init_mock();
+    api.transport = std::sync::Arc::new(EtagCache::new(api.client.clone()));
+
+    let body = serde_json::json! {{"gfyItem": crate::sample_gfy_item()}};
+    tk.block_on(async {
+        Mock::given(method("GET"))
+            .and(path("/gfycats/accomplishedfondkingsnake"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/gfycats/accomplishedfondkingsnake"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_json(body),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+    });
+
+    let first = tk.block_on(api.info("accomplishedfondkingsnake")).unwrap();
+    let second = tk.block_on(api.info("accomplishedfondkingsnake")).unwrap();
+    assert_eq! {first.title, "NYC Timelapse"};
+    assert_eq! {second.title, first.title};
+    assert_eq! {second.mp4_size, first.mp4_size};
+}
+
+#[test]
+fn oldest_evicted() {
+    use wiremock::matchers::method;
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, mut api) = crate::init_mock();
+    let cache = std::sync::Arc::new(EtagCache::new(api.client.clone()).capacity(2));
+    api.transport = cache.clone();
+
+    tk.block_on(
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_json(serde_json::json! {{"gfycats": []}}),
+            )
+            .mount(&server),
+    );
+
+    for path in ["me/one", "me/two", "me/three"] {
+        let response = tk.block_on(api.request(reqwest::Method::GET, path, None));
+        assert! {response.unwrap().status().is_success()};
+    }
+    assert_eq! {cache.len(), 2};
+    let cached = cache.entries.lock().unwrap().order.clone();
+    assert! {cached.iter().all(|url| !url.ends_with("/me/one"))};
+
+    cache.clear();
+    assert! {cache.is_empty()};
+}
+
+#[test]
+fn large_body_not_cached() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, mut api) = crate::init_mock();
+    let cache = std::sync::Arc::new(EtagCache::new(api.client.clone()).max_body_size(1024));
+    api.transport = cache.clone();
+
+    tk.block_on(
+        Mock::given(method("GET"))
+            .and(path("/big"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_bytes(vec![b'a'; 4096]),
+            )
+            .mount(&server),
+    );
+    // decompressed bodies have no known length, so the limit is found while reading
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    std::io::Write::write_all(&mut gzip, &[b'a'; 4096]).unwrap();
+    tk.block_on(
+        Mock::given(method("GET"))
+            .and(path("/big.gz"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .insert_header("Content-Encoding", "gzip")
+                    .set_body_bytes(gzip.finish().unwrap()),
+            )
+            .mount(&server),
+    );
+
+    // the body still arrives whole, it is just not kept
+    for path in ["big", "big.gz"] {
+        let response = tk
+            .block_on(api.request(reqwest::Method::GET, path, None))
+            .unwrap();
+        assert_eq! {tk.block_on(response.bytes()).unwrap(), vec![b'a'; 4096]};
+    }
+    assert! {cache.is_empty()};
+}
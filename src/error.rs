@@ -1,32 +1,107 @@
 use reqwest;
+use std::error::Error;
+use std::fmt;
 
 macro_rules! from {
     ($root:path, $destination_enum:ident :: $path_:ident) => {
         impl From<$root> for $destination_enum {
             fn from(e: $root) -> Self {
-                $destination_enum::$path_(e)
+                $destination_enum::$path_(e.into())
             }
         }
     };
 }
 
+/// Error raised by the http transport.
+///
+/// By default this is `reqwest::Error`. With the `opaque-errors` feature it is
+/// the crate owned `TransportError` instead, so `reqwest` types no longer appear
+/// in the public error signatures. The tradeoff is that the concrete
+/// `reqwest::Error` (status, url, `is_timeout()`, ...) can no longer be
+/// recovered by matching or downcasting; only its message is kept.
+#[cfg(not(feature = "opaque-errors"))]
+pub type TransportFailure = reqwest::Error;
+#[cfg(feature = "opaque-errors")]
+pub type TransportFailure = TransportError;
+
+/// `reqwest` independent transport error, see `TransportFailure`
+#[derive(Debug)]
+pub struct TransportError(pub String);
+
+impl From<reqwest::Error> for TransportError {
+    fn from(e: reqwest::Error) -> Self {
+        TransportError(e.to_string())
+    }
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for TransportError {}
+
 #[derive(Debug)]
 pub enum AuthError {
-    Request(reqwest::Error),
+    Request(TransportFailure),
     SerdeJson(serde_json::Error),
     IoError(std::io::Error),
     Expiration,
+    /// gfycat rejected the client id/secret
+    InvalidCredentials,
+    /// The handler was not created from credentials, so it cannot reauthorize
+    MissingCredentials,
+    /// Called outside of a tokio runtime. Run inside `#[tokio::main]` or use
+    /// `Api::new_blocking`
+    NoRuntime,
 }
 
 #[derive(Debug)]
 pub enum ApiError {
-    Request(reqwest::Error),
+    Request(TransportFailure),
     SerdeJson(serde_json::Error),
+    /// The response body of `endpoint` did not have the expected shape, e.g.
+    /// gfycat changed the type of a field
+    Decode {
+        endpoint: String,
+        source: serde_json::Error,
+    },
     IoError(std::io::Error),
     InvalidValue,
+    /// gfycat answered 404: the gfycat, user or followed user does not exist.
+    /// `info` and `info_lite` also report a successful response without a
+    /// `gfyItem` this way, as gfycat sends for some deleted or private
+    /// gfycats.
+    ///
+    /// Methods where a 404 carries a different meaning keep their own
+    /// mapping: `email_verified` (no or unverified email),
+    /// `send_email_verification` (`MissingEmail`), and `try_info` and
+    /// `user_exists`, which report absence in their `Ok` value.
+    NotFound,
     Unauthorized,
+    /// The token is valid but not allowed to perform the operation
+    Forbidden,
+    /// gfycat answered with a 5xx status. The fault is on gfycat's side and the
+    /// request may succeed if retried.
+    ServerError {
+        status: u16,
+    },
+    /// gfycat rejected the request with a 4xx status that has no variant of
+    /// its own, e.g. 400 for a bad request or 429 when rate limited
+    ClientError {
+        status: u16,
+    },
     Unknown,
     MissingEmail,
+    /// The response body was larger than `ApiBuilder::max_body_size` allows
+    BodyTooLarge {
+        limit: u64,
+    },
+    /// gfycat was still encoding `gfy_name` when `Api::wait_for_upload` gave up
+    UploadTimeout {
+        gfy_name: String,
+    },
 }
 from! {reqwest::Error, AuthError::Request}
 from! {serde_json::Error, AuthError::SerdeJson}
@@ -35,3 +110,98 @@ from! {std::io::Error, AuthError::IoError}
 from! {reqwest::Error, ApiError::Request}
 from! {serde_json::Error, ApiError::SerdeJson}
 from! {std::io::Error, ApiError::IoError}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::Request(_) => f.write_str("token request failed"),
+            AuthError::SerdeJson(_) => f.write_str("could not parse credentials or token"),
+            AuthError::IoError(_) => f.write_str("could not read credentials"),
+            AuthError::Expiration => f.write_str("token expiration is out of range"),
+            AuthError::InvalidCredentials => f.write_str("gfycat rejected the credentials"),
+            AuthError::MissingCredentials => f.write_str("no credentials to reauthorize with"),
+            AuthError::NoRuntime => f.write_str(
+                "no tokio runtime is running, use Api::new_blocking outside of async code",
+            ),
+        }
+    }
+}
+
+impl Error for AuthError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AuthError::Request(e) => Some(e),
+            AuthError::SerdeJson(e) => Some(e),
+            AuthError::IoError(e) => Some(e),
+            AuthError::Expiration
+            | AuthError::InvalidCredentials
+            | AuthError::MissingCredentials
+            | AuthError::NoRuntime => None,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Request(_) => f.write_str("request failed"),
+            ApiError::SerdeJson(_) => f.write_str("could not parse response"),
+            ApiError::Decode { endpoint, .. } => {
+                write!(f, "could not parse response of {}", endpoint)
+            }
+            ApiError::IoError(_) => f.write_str("io error"),
+            ApiError::InvalidValue => f.write_str("invalid value"),
+            ApiError::NotFound => f.write_str("not found"),
+            ApiError::Unauthorized => f.write_str("unauthorized"),
+            ApiError::Forbidden => f.write_str("forbidden"),
+            ApiError::ServerError { status } => write!(f, "gfycat server error {}", status),
+            ApiError::ClientError { status } => {
+                write!(f, "gfycat rejected the request with {}", status)
+            }
+            ApiError::Unknown => f.write_str("unknown error"),
+            ApiError::MissingEmail => f.write_str("account has no email"),
+            ApiError::BodyTooLarge { limit } => {
+                write!(f, "response body is larger than {} bytes", limit)
+            }
+            ApiError::UploadTimeout { gfy_name } => {
+                write!(f, "timed out waiting for {} to finish encoding", gfy_name)
+            }
+        }
+    }
+}
+
+impl ApiError {
+    /// Whether sending the same request again may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ApiError::ServerError { .. } | ApiError::ClientError { status: 429 }
+        )
+    }
+}
+
+impl Error for ApiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ApiError::Request(e) => Some(e),
+            ApiError::SerdeJson(e) => Some(e),
+            ApiError::Decode { source, .. } => Some(source),
+            ApiError::IoError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn source_chain() {
+    let json = serde_json::from_str::<u32>("nope").unwrap_err();
+    let err = ApiError::from(json);
+    assert! {err.source().unwrap().is::<serde_json::Error>()};
+    assert! {ApiError::Unauthorized.source().is_none()};
+}
+
+#[test]
+fn transport_error_message() {
+    let err = TransportError("connection reset".into());
+    assert_eq! {err.to_string(), "connection reset"};
+}
@@ -0,0 +1,159 @@
+//! A short list of the common operations, for getting started.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let gfycat = gfycat::Gfycat::connect("client id", "client secret").await?;
+//! let item = gfycat.info("https://gfycat.com/AccomplishedFondKingsnake").await?;
+//! let mp4 = gfycat.download(&item).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{ApiError, AuthError};
+use crate::upload::UploadParams;
+use crate::{Api, Category, GfyItem, LoadCredentials, SortOrder, User};
+use std::path::Path;
+
+/// Retries of failed idempotent requests a `Gfycat` makes
+const RETRIES: u32 = 2;
+
+/// The most common gfycat operations with sensible defaults, built on `Api`
+///
+/// Failed reads are retried twice, and gfycats can be named by id, name or
+/// url. Everything else, and finer control over what is here, is on the
+/// `Api` returned by `api()`.
+#[derive(Debug)]
+pub struct Gfycat {
+    api: Api,
+}
+
+impl Gfycat {
+    /// Authenticate with the client id and secret of your gfycat application
+    pub async fn connect(client_id: &str, client_secret: &str) -> Result<Gfycat, AuthError> {
+        let api = Api::builder()
+            .max_retries(RETRIES)
+            .build(client_id, client_secret)
+            .await?;
+        Ok(Gfycat { api })
+    }
+
+    /// Authenticate with the `client_id` and `client_secret` in a json file
+    pub async fn connect_with_file(path: &Path) -> Result<Gfycat, AuthError> {
+        let credentials = LoadCredentials::new(path)?;
+        let api = Api::builder()
+            .max_retries(RETRIES)
+            .build_from_owned_credentials(credentials)
+            .await?;
+        Ok(Gfycat { api })
+    }
+
+    /// Use an already configured handler
+    pub fn from_api(api: Api) -> Gfycat {
+        Gfycat { api }
+    }
+
+    /// The underlying handler, for everything this facade leaves out
+    pub fn api(&self) -> &Api {
+        &self.api
+    }
+
+    /// Get a new token once the current one has expired
+    pub async fn reauthorize(&mut self) -> Result<(), AuthError> {
+        self.api.reauthorize().await
+    }
+
+    /// The gfycat with the given id, name or url
+    pub async fn info(&self, gfycat: &str) -> Result<GfyItem, ApiError> {
+        let gfy_id = self.api.resolve_id(gfycat).await?;
+        self.api.info(&gfy_id).await
+    }
+
+    /// Several gfycats by id, name or url, in the order given
+    pub async fn info_many(&self, gfycats: &[&str]) -> Result<Vec<GfyItem>, ApiError> {
+        let mut gfy_ids = Vec::with_capacity(gfycats.len());
+        for gfycat in gfycats {
+            gfy_ids.push(self.api.resolve_id(gfycat).await?);
+        }
+
+        let gfy_ids = gfy_ids.iter().map(String::as_str).collect::<Vec<_>>();
+        self.api.info_many_strict(&gfy_ids, None).await
+    }
+
+    /// Gfycats similar to the one with the given id, name or url
+    pub async fn related(&self, gfycat: &str, count: u32) -> Result<Vec<GfyItem>, ApiError> {
+        let gfy_id = self.api.resolve_id(gfycat).await?;
+        self.api.related_gfycats(&gfy_id, count).await
+    }
+
+    /// The first `count` results of searching for `query`
+    pub async fn search(&self, query: &str, count: u32) -> Result<Vec<GfyItem>, ApiError> {
+        Ok(self.api.search(query, count, None).await?.gfycats)
+    }
+
+    /// The `count` gfycats trending right now
+    pub async fn trending(&self, count: u32) -> Result<Vec<GfyItem>, ApiError> {
+        let endpoint = self.api.url("gfycats/trending")?;
+        Ok(self.api.feed(endpoint, &[], count, None).await?.gfycats)
+    }
+
+    /// Trending tags, each with a few of its gfycats
+    pub async fn trending_tags(&self, count: u32) -> Result<Vec<Category>, ApiError> {
+        self.api.trending_categories(count).await
+    }
+
+    /// The public profile of a user
+    pub async fn user(&self, user_id: u64) -> Result<User, ApiError> {
+        self.api.user_details(user_id).await
+    }
+
+    /// The first `count` gfycats a user published, in `order`
+    pub async fn user_gfycats(
+        &self,
+        user_id: u64,
+        order: SortOrder,
+        count: u32,
+    ) -> Result<Vec<GfyItem>, ApiError> {
+        Ok(self
+            .api
+            .published(user_id, order, count, None)
+            .await?
+            .gfycats)
+    }
+
+    /// The best video of `item`: mp4, else webm, else the mobile mp4
+    pub async fn download(&self, item: &GfyItem) -> Result<Vec<u8>, ApiError> {
+        let url = item.best_video_url().ok_or(ApiError::NotFound)?;
+        self.api.download(url, None, None).await
+    }
+
+    /// Upload the video at `path` and return the name of the new gfycat
+    pub async fn upload(&self, path: &Path, title: &str) -> Result<String, ApiError> {
+        let params = UploadParams {
+            title: Some(title.to_owned()),
+            ..UploadParams::default()
+        };
+        self.api.upload_file(path, &params).await
+    }
+}
+
+#[test]
+fn facade_info_by_url() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, api) = crate::init_mock();
+    let body = serde_json::json! {{"gfyItem": crate::sample_gfy_item()}};
+    tk.block_on(
+        Mock::given(method("GET"))
+            .and(path("/gfycats/accomplishedfondkingsnake"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .expect(1)
+            .mount(&server),
+    );
+
+    let gfycat = Gfycat::from_api(api);
+    let left = tk
+        .block_on(gfycat.info("https://gfycat.com/AccomplishedFondKingsnake-new-york"))
+        .unwrap();
+    assert_eq! {left.title, "NYC Timelapse"};
+}
@@ -1,8 +1,3 @@
-use gfycat;
-
-use serde::{Deserialize, Serialize};
-use tokio;
-
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
     let api = gfycat::LoadCredentials::new(std::path::Path::new("config.json")).unwrap();
@@ -10,7 +5,11 @@ async fn main() {
 
     // let a = gc.email_verified().await;
     // let a =  gc.user_exists("@sypher0115").await;
-    let a = gc.info("accomplishedfondkingsnake").await;
-
-    dbg! {a};
+    match gc.info("accomplishedfondkingsnake").await {
+        Ok(item) => println!("{:#?}", item),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
 }
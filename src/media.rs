@@ -0,0 +1,208 @@
+use crate::{error, Api, ApiResult};
+use serde::{Deserialize, Serialize};
+
+/// A rendition gfycat encodes every upload into, as keyed in `content_urls`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rendition {
+    Mp4,
+    Webm,
+    Mobile,
+    MobilePoster,
+    LargeGif,
+    Max5mbGif,
+    Max2mbGif,
+    Max1mbGif,
+    Gif100px,
+    Webp,
+}
+
+impl Rendition {
+    /// Every rendition, in the order `content_urls` is documented
+    pub const ALL: [Rendition; 10] = [
+        Rendition::Mp4,
+        Rendition::Webm,
+        Rendition::Mobile,
+        Rendition::MobilePoster,
+        Rendition::LargeGif,
+        Rendition::Max5mbGif,
+        Rendition::Max2mbGif,
+        Rendition::Max1mbGif,
+        Rendition::Gif100px,
+        Rendition::Webp,
+    ];
+
+    /// Whether the rendition is a still image rather than an animation
+    pub fn is_poster(self) -> bool {
+        self == Rendition::MobilePoster
+    }
+}
+
+/// One entry of `content_urls`
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct MediaVariant {
+    pub url: String,
+    /// size in bytes as reported by gfycat
+    pub size: u64,
+    /// 0 when gfycat did not measure the rendition, as it does for `webp`
+    pub width: u64,
+    /// 0 when gfycat did not measure the rendition, as it does for `webp`
+    pub height: u64,
+}
+
+/// The `content_urls` object of a gfycat, listing each rendition with its size
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct ContentUrls {
+    pub mp4: Option<MediaVariant>,
+    pub webm: Option<MediaVariant>,
+    pub mobile: Option<MediaVariant>,
+    #[serde(rename = "mobilePoster")]
+    pub mobile_poster: Option<MediaVariant>,
+    #[serde(rename = "largeGif")]
+    pub large_gif: Option<MediaVariant>,
+    #[serde(rename = "max5mbGif")]
+    pub max_5mb_gif: Option<MediaVariant>,
+    #[serde(rename = "max2mbGif")]
+    pub max_2mb_gif: Option<MediaVariant>,
+    #[serde(rename = "max1mbGif")]
+    pub max_1mb_gif: Option<MediaVariant>,
+    #[serde(rename = "100pxGif")]
+    pub gif_100px: Option<MediaVariant>,
+    /// animated webp, listed with zero width and height
+    pub webp: Option<MediaVariant>,
+}
+
+impl ContentUrls {
+    /// The listed variant for `rendition`, if gfycat produced one
+    pub fn get(&self, rendition: Rendition) -> Option<&MediaVariant> {
+        match rendition {
+            Rendition::Mp4 => self.mp4.as_ref(),
+            Rendition::Webm => self.webm.as_ref(),
+            Rendition::Mobile => self.mobile.as_ref(),
+            Rendition::MobilePoster => self.mobile_poster.as_ref(),
+            Rendition::LargeGif => self.large_gif.as_ref(),
+            Rendition::Max5mbGif => self.max_5mb_gif.as_ref(),
+            Rendition::Max2mbGif => self.max_2mb_gif.as_ref(),
+            Rendition::Max1mbGif => self.max_1mb_gif.as_ref(),
+            Rendition::Gif100px => self.gif_100px.as_ref(),
+            Rendition::Webp => self.webp.as_ref(),
+        }
+    }
+}
+
+impl Api {
+    /// Download the media at `url`, e.g. a rendition listed in `content_urls`
+    ///
+    /// The token is not sent, the CDN does not need it. Compressed responses
+    /// are decoded unless `ApiBuilder::decompress_downloads` turned that off.
+    ///
+    /// `accept` is sent as the `Accept` header, e.g. `image/webp,image/*` to
+    /// ask for webp where the server offers a choice. gfycat's own hosts
+    /// (`giant.gfycat.com`, `thumbs.gfycat.com`) pick the format from the
+    /// file extension and ignore the header; it only matters for urls served
+    /// through a negotiating CDN or proxy.
+    ///
+    /// `timeout` replaces the client's default time limit, e.g. to give a
+    /// large mp4 longer than api calls get.
+    pub async fn download(
+        &self,
+        url: &str,
+        accept: Option<&str>,
+        timeout: Option<std::time::Duration>,
+    ) -> ApiResult<Vec<u8>> {
+        let mut request = self.client.get(url);
+        if let Some(accept) = accept {
+            request = request.header(reqwest::header::ACCEPT, accept);
+        }
+
+        let response = self
+            .send_through(&*self.downloads, request, timeout)
+            .await?;
+        let response = Self::check_status(response)?;
+
+        if !response.status().is_success() {
+            return Err(error::ApiError::Unknown);
+        }
+
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+#[test]
+fn download_accept_header() {
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, api) = crate::init_mock();
+    tk.block_on(
+        Mock::given(method("GET"))
+            .and(path("/AccomplishedFondKingsnake"))
+            .and(header("accept", "image/webp"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(&b"RIFF"[..]))
+            .expect(1)
+            .mount(&server),
+    );
+
+    let url = server.uri() + "/AccomplishedFondKingsnake";
+    let left = tk
+        .block_on(api.download(&url, Some("image/webp"), None))
+        .unwrap();
+    assert_eq! {left, b"RIFF"};
+}
+
+#[test]
+fn download_timeout() {
+    use std::time::Duration;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, api) = crate::init_mock();
+    tk.block_on(
+        Mock::given(method("GET"))
+            .and(path("/Large.mp4"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_bytes(&b"mp4"[..])
+                    .set_delay(Duration::from_millis(400)),
+            )
+            .mount(&server),
+    );
+
+    let url = server.uri() + "/Large.mp4";
+    let left = tk.block_on(api.download(&url, None, Some(Duration::from_millis(50))));
+    assert! {matches!(left, Err(error::ApiError::Request(_)))};
+
+    let left = tk.block_on(api.download(&url, None, Some(Duration::from_secs(5))));
+    assert_eq! {left.unwrap(), b"mp4"};
+}
+
+#[test]
+fn content_urls_sample() {
+    let info: serde_json::Value =
+        serde_json::from_str(include_str!("../test_data/accomplishedfondkingsnake.json")).unwrap();
+    let urls: ContentUrls =
+        serde_json::from_value(info["gfyItem"]["content_urls"].clone()).unwrap();
+
+    let mp4 = urls.get(Rendition::Mp4).unwrap();
+    assert_eq! {mp4.size, 14234971};
+    assert_eq! {mp4.width, 1920};
+    assert_eq! {urls.get(Rendition::Gif100px).unwrap().height, 158};
+}
+
+#[test]
+fn content_urls_webp() {
+    let urls: ContentUrls = serde_json::from_value(serde_json::json!({
+        "webp": {
+            "url": "https://thumbs.gfycat.com/AccomplishedFondKingsnake.webp",
+            "size": 1289824,
+            "height": 0,
+            "width": 0
+        }
+    }))
+    .unwrap();
+
+    let webp = urls.get(Rendition::Webp).unwrap();
+    assert_eq! {webp.url, "https://thumbs.gfycat.com/AccomplishedFondKingsnake.webp"};
+    assert_eq! {webp.size, 1289824};
+    assert_eq! {(webp.width, webp.height), (0, 0)};
+    assert! {urls.mp4.is_none()};
+}
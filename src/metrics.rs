@@ -0,0 +1,86 @@
+use std::fmt;
+use std::time;
+
+/// One request sent by `Api`, as passed to `Metrics::record`
+#[derive(Debug, Clone)]
+pub struct RequestMetric {
+    /// Endpoint with ids replaced by `{id}`, e.g. `gfycats/{id}/related`.
+    /// Requests outside the api (uploads, media urls) are labelled by host.
+    pub endpoint: String,
+    pub method: reqwest::Method,
+    /// Response status, `None` when no response was received
+    pub status: Option<u16>,
+    /// Name of the `ApiError` variant the answer maps to (`request`,
+    /// `server_error`, `unauthorized`, `forbidden`, `not_found`), if any
+    pub error: Option<&'static str>,
+    /// Time from sending the request until the response headers arrived
+    pub latency: time::Duration,
+}
+
+/// Receives a `RequestMetric` for every request `Api` sends, retries included.
+///
+/// Nothing is recorded unless a recorder is supplied through
+/// `ApiBuilder::metrics`. Implementations are called inline on the request
+/// path and should only update counters or histograms, e.g. of a prometheus
+/// registry.
+pub trait Metrics: fmt::Debug + Send + Sync {
+    fn record(&self, metric: &RequestMetric);
+}
+
+impl<T: Metrics + ?Sized> Metrics for std::sync::Arc<T> {
+    fn record(&self, metric: &RequestMetric) {
+        (**self).record(metric)
+    }
+}
+
+/// Low cardinality label of `url`, relative to the api `base`
+pub(crate) fn endpoint_label(base: &str, url: &reqwest::Url) -> String {
+    let path = match url.as_str().strip_prefix(base.trim_end_matches('/')) {
+        Some(path) => path.split(&['?', '#'][..]).next().unwrap_or_default(),
+        None => return url.host_str().unwrap_or_default().to_owned(),
+    };
+
+    let mut previous = "";
+    let segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            let id = matches!(previous, "users" | "gfycats" | "following" | "status")
+                && !matches!(segment, "search" | "trending" | "fetch");
+            previous = segment;
+            if id {
+                "{id}"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>();
+
+    segments.join("/")
+}
+
+/// `ApiError` variant name for a response status, if it is an error
+pub(crate) fn status_error(status: reqwest::StatusCode) -> Option<&'static str> {
+    match status.as_u16() {
+        401 => Some("unauthorized"),
+        403 => Some("forbidden"),
+        404 => Some("not_found"),
+        500..=599 => Some("server_error"),
+        _ => None,
+    }
+}
+
+#[test]
+fn endpoint_labels() {
+    let base = "https://api.gfycat.com/v1/";
+    let label = |url: &str| endpoint_label(base, &reqwest::Url::parse(url).unwrap());
+
+    assert_eq! {label("https://api.gfycat.com/v1/gfycats/accomplishedfondkingsnake"), "gfycats/{id}"};
+    assert_eq! {label("https://api.gfycat.com/v1/gfycats/abc/related?count=5"), "gfycats/{id}/related"};
+    assert_eq! {label("https://api.gfycat.com/v1/gfycats/search?search_text=cats"), "gfycats/search"};
+    assert_eq! {label("https://api.gfycat.com/v1/users/42/gfycats"), "users/{id}/gfycats"};
+    assert_eq! {label("https://api.gfycat.com/v1/gfycats/fetch/status/NewUpload"), "gfycats/fetch/status/{id}"};
+    assert_eq! {label("https://api.gfycat.com/v1/me/following/egster"), "me/following/{id}"};
+    assert_eq! {label("https://api.gfycat.com/v1/me/follows/gfycats"), "me/follows/gfycats"};
+    assert_eq! {label("https://filedrop.gfycat.com/NewUpload"), "filedrop.gfycat.com"};
+}
@@ -0,0 +1,17 @@
+//! The types most programs need, for a single glob import.
+//!
+//! ```
+//! use gfycat::prelude::*;
+//!
+//! fn pick(item: &GfyItem) -> Option<&MediaVariant> {
+//!     item.content_urls.as_ref()?.get(Rendition::Mp4)
+//! }
+//! # let _ = pick;
+//! ```
+
+pub use crate::builder::ApiBuilder;
+pub use crate::error::{ApiError, AuthError};
+pub use crate::facade::Gfycat;
+pub use crate::media::{ContentUrls, MediaVariant, Rendition};
+pub use crate::upload::{UploadParams, UploadStatus};
+pub use crate::{Api, FeedPage, GfyItem, GfycatInfo, SortOrder, ThumbSize, User, Username};
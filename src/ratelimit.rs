@@ -0,0 +1,107 @@
+use crate::transport::{ResponseFuture, Transport};
+use std::sync::Mutex;
+use std::time;
+
+/// Tokens left in the bucket as of `updated`. Negative while callers are
+/// waiting for tokens they already reserved.
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: time::Instant,
+}
+
+/// Transport that spaces requests out with a token bucket.
+///
+/// The bucket holds up to `burst` tokens and refills at `per_second`. Every
+/// request takes one token, waiting for it when the bucket is empty, so bursts
+/// are sent straight away and sustained traffic stays under the rate instead
+/// of running into gfycat's `429 Too Many Requests`. Waiting requests are sent
+/// in the order they arrived.
+///
+/// Enable it with `ApiBuilder::rate_limit`.
+#[derive(Debug)]
+pub struct RateLimit<T> {
+    inner: T,
+    per_second: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+impl<T: Transport> RateLimit<T> {
+    /// Panics unless `per_second` is positive and `burst` at least 1
+    pub fn new(inner: T, per_second: f64, burst: u32) -> Self {
+        assert!(per_second > 0.0, "rate limit must be positive");
+        assert!(burst >= 1, "burst must allow at least one request");
+
+        RateLimit {
+            inner,
+            per_second,
+            burst: burst as f64,
+            bucket: Mutex::new(Bucket {
+                tokens: burst as f64,
+                updated: time::Instant::now(),
+            }),
+        }
+    }
+
+    /// Take a token and return how long to wait until it is available
+    fn reserve(&self) -> time::Duration {
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = time::Instant::now();
+
+        let refill = now.duration_since(bucket.updated).as_secs_f64() * self.per_second;
+        bucket.tokens = (bucket.tokens + refill).min(self.burst) - 1.0;
+        bucket.updated = now;
+
+        if bucket.tokens >= 0.0 {
+            time::Duration::from_secs(0)
+        } else {
+            time::Duration::from_secs_f64(-bucket.tokens / self.per_second)
+        }
+    }
+
+    async fn execute_limited(
+        &self,
+        request: reqwest::Request,
+    ) -> reqwest::Result<reqwest::Response> {
+        let wait = self.reserve();
+        if wait > time::Duration::from_secs(0) {
+            tokio::time::sleep(wait).await;
+        }
+
+        self.inner.execute(request).await
+    }
+}
+
+impl<T: Transport> Transport for RateLimit<T> {
+    fn execute(&self, request: reqwest::Request) -> ResponseFuture<'_> {
+        Box::pin(self.execute_limited(request))
+    }
+}
+
+#[cfg(test)]
+fn timed_requests(per_second: f64, burst: u32, count: usize) -> time::Duration {
+    let (tk, _server, mut api) = crate::init_mock();
+    let canned = crate::transport::CannedTransport::new(200, "{}");
+    api.transport = std::sync::Arc::new(RateLimit::new(canned, per_second, burst));
+
+    let started = time::Instant::now();
+    for _ in 0..count {
+        tk.block_on(api.request(reqwest::Method::GET, "me", None))
+            .unwrap();
+    }
+    started.elapsed()
+}
+
+#[test]
+fn requests_spaced_by_rate() {
+    // the first request uses the single token, the other three wait 50ms each
+    let elapsed = timed_requests(20.0, 1, 4);
+    assert! {elapsed >= time::Duration::from_millis(150), "{:?}", elapsed};
+}
+
+#[test]
+fn burst_sent_immediately() {
+    let elapsed = timed_requests(1.0, 3, 3);
+    assert! {elapsed < time::Duration::from_millis(500), "{:?}", elapsed};
+}
@@ -0,0 +1,115 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Future returned by `Transport::execute`
+pub type ResponseFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<reqwest::Response, reqwest::Error>> + Send + 'a>>;
+
+/// Sends the requests built by `Api`.
+///
+/// `reqwest::Client` is the transport used unless another one is supplied through
+/// `ApiBuilder::transport`. Swapping it out lets tests answer requests with canned
+/// responses without any network or mock server.
+pub trait Transport: fmt::Debug + Send + Sync {
+    fn execute(&self, request: reqwest::Request) -> ResponseFuture<'_>;
+}
+
+impl Transport for reqwest::Client {
+    fn execute(&self, request: reqwest::Request) -> ResponseFuture<'_> {
+        Box::pin(reqwest::Client::execute(self, request))
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for std::sync::Arc<T> {
+    fn execute(&self, request: reqwest::Request) -> ResponseFuture<'_> {
+        (**self).execute(request)
+    }
+}
+
+/// Answers every request with the same status and body, remembering what was sent
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub(crate) struct CannedTransport {
+    pub status: u16,
+    pub body: String,
+    pub requests: std::sync::Mutex<Vec<(reqwest::Method, String)>>,
+    /// how long each answer takes
+    pub delay: std::time::Duration,
+    pub in_flight: std::sync::atomic::AtomicUsize,
+    /// most requests that were waiting for an answer at once
+    pub max_in_flight: std::sync::atomic::AtomicUsize,
+}
+
+#[cfg(test)]
+impl CannedTransport {
+    pub fn new(status: u16, body: &str) -> Self {
+        CannedTransport {
+            status,
+            body: body.into(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+impl Transport for CannedTransport {
+    fn execute(&self, request: reqwest::Request) -> ResponseFuture<'_> {
+        self.requests
+            .lock()
+            .unwrap()
+            .push((request.method().clone(), request.url().to_string()));
+
+        let response = http::Response::builder()
+            .status(self.status)
+            .body(self.body.clone())
+            .unwrap();
+
+        Box::pin(async move {
+            use std::sync::atomic::Ordering;
+
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            Ok(response.into())
+        })
+    }
+}
+
+#[test]
+fn canned_info() {
+    let body = serde_json::json! {{"gfyItem": crate::sample_gfy_item()}}.to_string();
+    let transport = std::sync::Arc::new(CannedTransport::new(200, &body));
+    let api = crate::Api {
+        transport: transport.clone(),
+        token: "Bearer mock".into(),
+        ..crate::Api::default()
+    };
+
+    let tk = tokio::runtime::Runtime::new().unwrap();
+    let left = tk.block_on(api.info("accomplishedfondkingsnake")).unwrap();
+    assert_eq! {left.gfy_name, "AccomplishedFondKingsnake"};
+
+    let requests = transport.requests.lock().unwrap();
+    assert_eq! {requests.len(), 1};
+    assert_eq! {requests[0].0, reqwest::Method::GET};
+    assert_eq! {requests[0].1, "https://api.gfycat.com/v1/gfycats/accomplishedfondkingsnake"};
+}
+
+#[test]
+fn canned_unauthorized() {
+    let transport = std::sync::Arc::new(CannedTransport::new(401, ""));
+    let api = crate::Api {
+        transport: transport.clone(),
+        token: "Bearer t".into(),
+        ..crate::Api::default()
+    };
+
+    let tk = tokio::runtime::Runtime::new().unwrap();
+    let left = tk.block_on(api.user_exists("@egster"));
+    assert! {matches!(left, Err(crate::error::ApiError::Unauthorized))};
+    // the 401 came from the transport, not the anonymous guard
+    assert_eq! {transport.requests.lock().unwrap().len(), 1};
+}
@@ -0,0 +1,572 @@
+use crate::{error, join_url, Api, ApiResult};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// Options for a new upload, sent when the upload key is requested
+///
+/// Only file uploads (`Api::upload_file`) are supported; gfycat's `fetchUrl`
+/// uploads from a url are not. Its `fetchHours`, `fetchMinutes` and
+/// `fetchSeconds` options belong to those and set where in the source video
+/// the clip starts; they are not an expiry or retry window for the fetch.
+#[derive(Serialize, Debug, Default, Clone)]
+pub struct UploadParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// upload even if gfycat already has a video with the same md5
+    #[serde(rename = "noMd5")]
+    pub no_md5: bool,
+    #[serde(rename = "keepAudio")]
+    pub keep_audio: bool,
+    #[serde(serialize_with = "as_flag")]
+    pub private: bool,
+    /// time limit for the upload requests, replacing the client's default.
+    /// Large files on slow connections can take minutes.
+    #[serde(skip)]
+    pub timeout: Option<std::time::Duration>,
+}
+
+fn as_flag<S: serde::Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u8(*value as u8)
+}
+
+/// Response to an upload key request
+#[derive(Deserialize, Debug)]
+struct UploadKey {
+    #[serde(rename = "gfyname")]
+    gfy_name: String,
+}
+
+/// Encoding progress of an upload, see `Api::upload_status`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadStatus {
+    /// gfycat is still encoding the file
+    Encoding,
+    /// The gfycat is ready. `gfy_name` differs from the uploaded name when
+    /// gfycat already had the same video and returned the existing gfycat.
+    Complete { gfy_name: String },
+    /// gfycat has no upload under the name, or has not started on it yet
+    NotFound,
+    /// Encoding failed, with gfycat's description of the problem
+    Failed(String),
+}
+
+/// Response of `gfycats/fetch/status/{gfyName}`
+#[derive(Deserialize, Debug)]
+struct StatusResponse {
+    task: String,
+    #[serde(rename = "gfyname")]
+    gfy_name: Option<String>,
+    description: Option<String>,
+}
+
+impl StatusResponse {
+    fn into_status(self, uploaded_name: &str) -> UploadStatus {
+        match self.task.as_str() {
+            "encoding" => UploadStatus::Encoding,
+            "complete" => UploadStatus::Complete {
+                gfy_name: self.gfy_name.unwrap_or_else(|| uploaded_name.to_owned()),
+            },
+            // gfycat really does spell it this way
+            "NotFoundo" => UploadStatus::NotFound,
+            _ => UploadStatus::Failed(self.description.unwrap_or(self.task)),
+        }
+    }
+}
+
+impl Api {
+    /// Upload the file at `path` and return the `gfyName` gfycat assigned to it.
+    ///
+    /// The file is streamed from disk, so memory use stays constant regardless of
+    /// its size. Encoding happens after this returns.
+    ///
+    /// gfycat has no idempotency key, so retries are made safe on the client:
+    /// the upload key is requested once, and a failed transfer (5xx or
+    /// connection error) is retried up to `max_retries` times against the same
+    /// `gfyName` instead of creating a new gfycat.
+    pub async fn upload_file(&self, path: &Path, params: &UploadParams) -> ApiResult<String> {
+        // fail on a missing file before a gfycat is created for it
+        tokio::fs::metadata(path).await?;
+
+        let gfy_name = self.upload_key(params).await?;
+        self.uploads.lock().unwrap().push(gfy_name.clone());
+
+        let mut retries = self.max_retries;
+        let mut backoff = crate::RETRY_BACKOFF;
+
+        loop {
+            match self.transfer_file(path, &gfy_name, params).await {
+                Err(e)
+                    if retries > 0
+                        && (e.is_retryable() || matches!(e, error::ApiError::Request(_))) =>
+                {
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    retries -= 1;
+                }
+                result => return result.map(|()| gfy_name),
+            }
+        }
+    }
+
+    /// Ask gfycat how far encoding of the upload `gfy_name` has progressed
+    ///
+    /// `timeout` replaces the client's default time limit for the request.
+    pub async fn upload_status(
+        &self,
+        gfy_name: &str,
+        timeout: Option<Duration>,
+    ) -> ApiResult<UploadStatus> {
+        let endpoint = self.url(&format!("gfycats/fetch/status/{}", gfy_name))?;
+
+        let request = self
+            .client
+            .get(endpoint)
+            .header(reqwest::header::AUTHORIZATION, &self.token);
+
+        let response = Self::check_status(self.send_with_timeout(request, timeout).await?)?;
+        Ok(self
+            .json::<StatusResponse>(response)
+            .await?
+            .into_status(gfy_name))
+    }
+
+    /// Poll `upload_status` every `poll_interval` until gfycat has finished
+    /// encoding `gfy_name`, returning the `Complete` or `Failed` status
+    ///
+    /// `NotFound` is polled through like `Encoding`, since gfycat reports it
+    /// for uploads it has not started on yet. `timeout` bounds the whole wait,
+    /// not each poll; on expiry `ApiError::UploadTimeout` is returned.
+    /// `poll_timeout`, when given, limits each status request instead of the
+    /// client's default.
+    pub async fn wait_for_upload(
+        &self,
+        gfy_name: &str,
+        poll_interval: Duration,
+        timeout: Duration,
+        poll_timeout: Option<Duration>,
+    ) -> ApiResult<UploadStatus> {
+        self.wait_for_upload_with_progress(gfy_name, poll_interval, timeout, poll_timeout, |_| {})
+            .await
+    }
+
+    /// `wait_for_upload`, calling `progress` with the status of every poll
+    pub async fn wait_for_upload_with_progress<F: FnMut(&UploadStatus)>(
+        &self,
+        gfy_name: &str,
+        poll_interval: Duration,
+        timeout: Duration,
+        poll_timeout: Option<Duration>,
+        mut progress: F,
+    ) -> ApiResult<UploadStatus> {
+        let poll = async {
+            loop {
+                let status = self.upload_status(gfy_name, poll_timeout).await?;
+                progress(&status);
+
+                match status {
+                    UploadStatus::Encoding | UploadStatus::NotFound => {
+                        tokio::time::sleep(poll_interval).await
+                    }
+                    settled => return Ok(settled),
+                }
+            }
+        };
+
+        tokio::time::timeout(timeout, poll)
+            .await
+            .unwrap_or_else(|_| {
+                Err(error::ApiError::UploadTimeout {
+                    gfy_name: gfy_name.to_owned(),
+                })
+            })
+    }
+
+    /// `gfyName`s of the files uploaded through this handler that gfycat is
+    /// still encoding
+    ///
+    /// gfycat has no endpoint listing a user's pending uploads, and unfinished
+    /// gfycats do not appear in `me/gfycats`. Instead the handler remembers
+    /// every name `upload_file` was given and polls `upload_status` for each.
+    /// `NotFound` counts as processing, since gfycat reports it for uploads it
+    /// has not started on yet. Uploads found complete or failed are forgotten,
+    /// so later calls only poll what may still be encoding. Uploads made by
+    /// other handlers or processes are not seen.
+    ///
+    /// An upload whose poll fails is logged, left out of the result and kept
+    /// for the next call. Only when every poll fails is the first error
+    /// returned.
+    pub async fn processing_gfycats(&self) -> ApiResult<Vec<String>> {
+        let uploads = self.uploads.lock().unwrap().clone();
+
+        let statuses = stream::iter(uploads)
+            .map(|gfy_name| async move {
+                let status = self.upload_status(&gfy_name, None).await;
+                (gfy_name, status)
+            })
+            .buffered(self.concurrency(None))
+            .collect::<Vec<_>>()
+            .await;
+
+        let polled = statuses.len();
+        let mut processing = Vec::new();
+        let mut settled = Vec::new();
+        let mut errors = Vec::new();
+        for (gfy_name, status) in statuses {
+            match status {
+                Ok(UploadStatus::Encoding) | Ok(UploadStatus::NotFound) => {
+                    processing.push(gfy_name)
+                }
+                Ok(_) => settled.push(gfy_name),
+                Err(e) => {
+                    tracing::warn!("could not poll upload status of {}: {}", gfy_name, e);
+                    errors.push(e);
+                }
+            }
+        }
+
+        self.uploads
+            .lock()
+            .unwrap()
+            .retain(|gfy_name| !settled.contains(gfy_name));
+
+        if polled > 0 && errors.len() == polled {
+            return Err(errors.remove(0));
+        }
+        Ok(processing)
+    }
+
+    /// Stream the file at `path` to the filedrop under `gfy_name`
+    async fn transfer_file(
+        &self,
+        path: &Path,
+        gfy_name: &str,
+        params: &UploadParams,
+    ) -> ApiResult<()> {
+        let file = tokio::fs::File::open(path).await?;
+        let length = file.metadata().await?.len();
+
+        let endpoint = join_url(&self.filedrop, gfy_name);
+        let body = reqwest::Body::wrap_stream(tokio_util::io::ReaderStream::new(file));
+
+        let request = self
+            .client
+            .put(&endpoint)
+            .header(reqwest::header::CONTENT_LENGTH, length)
+            .body(body);
+
+        let response = self.send_with_timeout(request, params.timeout).await?;
+
+        match response.status().as_u16() {
+            200 | 201 | 204 => Ok(()),
+            _ => Err(error::ApiError::Unknown),
+        }
+    }
+
+    /// Ask gfycat for the name a new upload will be stored under
+    async fn upload_key(&self, params: &UploadParams) -> ApiResult<String> {
+        let endpoint = self.url("gfycats")?;
+
+        let request = self
+            .client
+            .post(endpoint)
+            .header(reqwest::header::AUTHORIZATION, &self.token)
+            .json(params);
+
+        let response = self.send_with_timeout(request, params.timeout).await?;
+
+        match response.status().as_u16() {
+            200 => Ok(self.json::<UploadKey>(response).await?.gfy_name),
+            400 | 422 => Err(error::ApiError::InvalidValue),
+            401 => Err(error::ApiError::Unauthorized),
+            _ => Err(error::ApiError::Unknown),
+        }
+    }
+}
+
+#[test]
+fn upload_params_body() {
+    let params = UploadParams {
+        title: Some("nyc".into()),
+        tags: vec!["timelapse".into()],
+        private: true,
+        ..UploadParams::default()
+    };
+    let left = serde_json::to_value(&params).unwrap();
+    assert_eq! {left, serde_json::json! {
+        {"title": "nyc", "tags": ["timelapse"], "noMd5": false, "keepAudio": false, "private": 1}
+    }};
+}
+
+#[test]
+fn upload_file_streams() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, mut api) = crate::init_mock();
+    api.filedrop = server.uri() + "/filedrop/";
+
+    let file = std::env::temp_dir().join(format!("gfycat-upload-{}.mp4", std::process::id()));
+    std::fs::write(&file, vec![7u8; 256 * 1024]).unwrap();
+
+    tk.block_on(async {
+        Mock::given(method("POST"))
+            .and(path("/gfycats"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json! {{"isOk": true, "gfyname": "NewUpload"}}),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/filedrop/NewUpload"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+    });
+
+    let left = tk.block_on(api.upload_file(&file, &UploadParams::default()));
+    std::fs::remove_file(&file).unwrap();
+    assert_eq! {left.unwrap(), "NewUpload"};
+
+    let requests = tk.block_on(server.received_requests()).unwrap();
+    let put = requests
+        .iter()
+        .find(|r| r.method.as_str() == "PUT")
+        .unwrap();
+    assert_eq! {put.body.len(), 256 * 1024};
+    assert_eq! {put.headers["content-length"], "262144"};
+}
+
+#[test]
+fn upload_timeout_override() {
+    use std::time::Duration;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, mut api) = crate::init_mock();
+    api.filedrop = server.uri() + "/filedrop/";
+    api.client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    api.transport = std::sync::Arc::new(api.client.clone());
+
+    let file = std::env::temp_dir().join(format!("gfycat-timeout-{}.mp4", std::process::id()));
+    std::fs::write(&file, vec![7u8; 1024]).unwrap();
+
+    tk.block_on(async {
+        Mock::given(method("POST"))
+            .and(path("/gfycats"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json! {{"isOk": true, "gfyname": "SlowUpload"}}),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/filedrop/SlowUpload"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(400)))
+            .mount(&server)
+            .await;
+    });
+
+    let left = tk.block_on(api.upload_file(&file, &UploadParams::default()));
+    assert! {matches!(left, Err(error::ApiError::Request(_)))};
+
+    let params = UploadParams {
+        timeout: Some(Duration::from_secs(5)),
+        ..UploadParams::default()
+    };
+    let left = tk.block_on(api.upload_file(&file, &params));
+    std::fs::remove_file(&file).unwrap();
+    assert_eq! {left.unwrap(), "SlowUpload"};
+}
+
+#[test]
+fn upload_retry_reuses_key() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, mut api) = crate::init_mock();
+    api.filedrop = server.uri() + "/filedrop/";
+    api.max_retries = 2;
+
+    let file = std::env::temp_dir().join(format!("gfycat-retry-{}.mp4", std::process::id()));
+    std::fs::write(&file, vec![7u8; 1024]).unwrap();
+
+    tk.block_on(async {
+        Mock::given(method("POST"))
+            .and(path("/gfycats"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json! {{"isOk": true, "gfyname": "OnlyOnce"}}),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/filedrop/OnlyOnce"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/filedrop/OnlyOnce"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+    });
+
+    let left = tk.block_on(api.upload_file(&file, &UploadParams::default()));
+    std::fs::remove_file(&file).unwrap();
+    assert_eq! {left.unwrap(), "OnlyOnce"};
+
+    let requests = tk.block_on(server.received_requests()).unwrap();
+    let put = requests.iter().filter(|r| r.method.as_str() == "PUT");
+    assert! {put.map(|r| r.body.len()).all(|len| len == 1024)};
+}
+
+#[test]
+fn processing_uploads() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, api) = crate::init_mock();
+    let statuses = [
+        (
+            "EncodingOne",
+            serde_json::json! {{"task": "encoding", "time": 10}},
+        ),
+        (
+            "EncodingTwo",
+            serde_json::json! {{"task": "encoding", "time": 4}},
+        ),
+        (
+            "DoneOne",
+            serde_json::json! {{"task": "complete", "gfyname": "DoneOne"}},
+        ),
+        (
+            "BrokenOne",
+            serde_json::json! {{"task": "error", "description": "bad codec"}},
+        ),
+        ("PendingOne", serde_json::json! {{"task": "NotFoundo"}}),
+    ];
+    tk.block_on(async {
+        for (gfy_name, body) in &statuses {
+            Mock::given(method("GET"))
+                .and(path(format!("/gfycats/fetch/status/{}", gfy_name)))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/gfycats/fetch/status/FlakyOne"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+    });
+    api.uploads
+        .lock()
+        .unwrap()
+        .extend(statuses.iter().map(|(gfy_name, _)| gfy_name.to_string()));
+    api.uploads.lock().unwrap().push("FlakyOne".into());
+
+    // the failed poll is kept for next time but not reported
+    let left = tk.block_on(api.processing_gfycats()).unwrap();
+    assert_eq! {left, ["EncodingOne", "EncodingTwo", "PendingOne"]};
+    assert_eq! {
+        *api.uploads.lock().unwrap(),
+        ["EncodingOne", "EncodingTwo", "PendingOne", "FlakyOne"]
+    };
+
+    // with every poll failing the error is returned, and nothing is forgotten
+    api.uploads
+        .lock()
+        .unwrap()
+        .retain(|name| name == "FlakyOne");
+    let left = tk.block_on(api.processing_gfycats());
+    assert! {matches!(left, Err(error::ApiError::ServerError { status: 500 }))};
+    assert_eq! {*api.uploads.lock().unwrap(), ["FlakyOne"]};
+
+    let status = tk.block_on(api.upload_status("BrokenOne", None)).unwrap();
+    assert_eq! {status, UploadStatus::Failed("bad codec".into())};
+}
+
+#[test]
+fn wait_for_upload() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, api) = crate::init_mock();
+    tk.block_on(async {
+        // mounted first, so it answers the first three polls
+        Mock::given(method("GET"))
+            .and(path("/gfycats/fetch/status/SlowOne"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json! {{"task": "encoding"}}),
+            )
+            .up_to_n_times(3)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/gfycats/fetch/status/SlowOne"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json! {{"task": "complete", "gfyname": "SlowOne"}}),
+            )
+            .mount(&server)
+            .await;
+    });
+
+    let mut seen = Vec::new();
+    let left = tk
+        .block_on(api.wait_for_upload_with_progress(
+            "SlowOne",
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+            Some(Duration::from_secs(1)),
+            |status| seen.push(status.clone()),
+        ))
+        .unwrap();
+    let done = UploadStatus::Complete {
+        gfy_name: "SlowOne".into(),
+    };
+    assert_eq! {left, done};
+    assert_eq! {seen.len(), 4};
+    assert! {seen[..3].iter().all(|status| *status == UploadStatus::Encoding)};
+}
+
+#[test]
+fn wait_for_upload_timeout() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (tk, server, api) = crate::init_mock();
+    tk.block_on(
+        Mock::given(method("GET"))
+            .and(path("/gfycats/fetch/status/StuckOne"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json! {{"task": "encoding"}}),
+            )
+            .mount(&server),
+    );
+
+    let started = std::time::Instant::now();
+    let left = tk.block_on(api.wait_for_upload(
+        "StuckOne",
+        Duration::from_millis(10),
+        Duration::from_millis(100),
+        None,
+    ));
+    assert! {matches!(left, Err(error::ApiError::UploadTimeout { gfy_name }) if gfy_name == "StuckOne")};
+    assert! {started.elapsed() < Duration::from_secs(2)};
+}